@@ -0,0 +1,119 @@
+// Self-contained BlurHash encoder (https://blurha.sh) used to give the
+// frontend a tiny placeholder string for a thumbnail before the full image
+// has loaded. No extra crate is pulled in for this - it's a short, well
+// defined algorithm that's cheaper to inline than to vendor.
+
+const BASE83_CHARS: &[u8] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz#$%*+,-.:;=?@[]^_{|}~";
+
+fn base83_encode(mut value: u64, length: usize) -> String {
+    let mut result = vec![0u8; length];
+    for i in (0..length).rev() {
+        let digit = (value % 83) as usize;
+        result[i] = BASE83_CHARS[digit];
+        value /= 83;
+    }
+    String::from_utf8(result).unwrap()
+}
+
+fn srgb_to_linear(channel: u8) -> f64 {
+    let c = channel as f64 / 255.0;
+    ((c + 0.055) / 1.055).powf(2.4)
+}
+
+fn linear_to_srgb(value: f64) -> u8 {
+    let clamped = value.clamp(0.0, 1.0);
+    ((1.055 * clamped.powf(1.0 / 2.4) - 0.055) * 255.0).round() as u8
+}
+
+fn sign_pow(value: f64, exp: f64) -> f64 {
+    value.signum() * value.abs().powf(exp)
+}
+
+fn encode_dc(rgb: [f64; 3]) -> u64 {
+    let r = linear_to_srgb(rgb[0]) as u64;
+    let g = linear_to_srgb(rgb[1]) as u64;
+    let b = linear_to_srgb(rgb[2]) as u64;
+    (r << 16) + (g << 8) + b
+}
+
+fn encode_ac(rgb: [f64; 3], max_ac: f64) -> u64 {
+    let quantize = |value: f64| -> u64 {
+        (sign_pow(value / max_ac, 0.5) * 9.0 + 9.5)
+            .floor()
+            .clamp(0.0, 18.0) as u64
+    };
+    let (r, g, b) = (quantize(rgb[0]), quantize(rgb[1]), quantize(rgb[2]));
+    (r * 19 + g) * 19 + b
+}
+
+// Computes the `(x, y)` DCT component for the given component indices over
+// the whole image, in the image's linear-light RGB space.
+fn component_factor(img: &image::RgbImage, i: u32, j: u32) -> [f64; 3] {
+    let (width, height) = (img.width(), img.height());
+    let normalization = if i == 0 && j == 0 { 1.0 } else { 2.0 };
+    let mut sum = [0f64; 3];
+    for y in 0..height {
+        for x in 0..width {
+            let basis = normalization
+                * (std::f64::consts::PI * i as f64 * x as f64 / width as f64).cos()
+                * (std::f64::consts::PI * j as f64 * y as f64 / height as f64).cos();
+            let pixel = img.get_pixel(x, y);
+            sum[0] += basis * srgb_to_linear(pixel[0]);
+            sum[1] += basis * srgb_to_linear(pixel[1]);
+            sum[2] += basis * srgb_to_linear(pixel[2]);
+        }
+    }
+    let scale = 1.0 / (width * height) as f64;
+    [sum[0] * scale, sum[1] * scale, sum[2] * scale]
+}
+
+/// Encodes an RGB image into a BlurHash string with `x_components` by
+/// `y_components` DCT components (each clamped to 1..=9).
+pub fn encode(img: &image::RgbImage, x_components: u32, y_components: u32) -> String {
+    let x_components = x_components.clamp(1, 9);
+    let y_components = y_components.clamp(1, 9);
+
+    // The DCT is over every pixel, so work on a small copy for large images.
+    let small = if img.width() > 100 || img.height() > 100 {
+        image::imageops::resize(
+            img,
+            img.width().min(100),
+            img.height().min(100),
+            image::imageops::FilterType::Triangle,
+        )
+    } else {
+        img.clone()
+    };
+
+    let mut factors = Vec::with_capacity((x_components * y_components) as usize);
+    for j in 0..y_components {
+        for i in 0..x_components {
+            factors.push(component_factor(&small, i, j));
+        }
+    }
+
+    let dc = factors[0];
+    let ac = &factors[1..];
+
+    let max_ac = ac
+        .iter()
+        .flat_map(|c| c.iter().map(|v| v.abs()))
+        .fold(0f64, f64::max);
+    let (quantized_max_ac, max_ac_for_quant) = if ac.is_empty() {
+        (0u64, 1.0)
+    } else {
+        let quantized = ((max_ac * 166.0 - 0.5).floor().clamp(0.0, 82.0)) as u64;
+        (quantized, (quantized as f64 + 1.0) / 166.0)
+    };
+
+    let size_flag = (x_components - 1) + (y_components - 1) * 9;
+    let mut hash = String::new();
+    hash.push_str(&base83_encode(size_flag as u64, 1));
+    hash.push_str(&base83_encode(quantized_max_ac, 1));
+    hash.push_str(&base83_encode(encode_dc(dc), 4));
+    for component in ac {
+        hash.push_str(&base83_encode(encode_ac(*component, max_ac_for_quant), 2));
+    }
+    hash
+}
@@ -7,7 +7,9 @@ use std::fs::File;
 use std::io::Cursor;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::mpsc::channel;
+use std::sync::Arc;
 use std::thread; // Import the thread module
 use tauri::command;
 use tauri::{AppHandle, Emitter};
@@ -15,14 +17,80 @@ use tauri_plugin_dialog::{DialogExt, FilePath}; // Add Deserialize
 
 use dirs;
 use image::ImageFormat;
+use rayon::prelude::*;
+use rgb::FromSlice;
 use webp::Encoder;
 
+mod blurhash;
+
 // Struct for the frontend to send a list of files
 #[derive(Deserialize)]
 struct ConversionJob {
     files: Vec<String>,
     format: String,
     bg_color: Option<String>,
+    quality: Option<u8>,
+    effort: Option<u8>,
+    lossless: Option<bool>,
+    #[serde(default)]
+    ops: Vec<ProcessOp>,
+}
+
+// A single image-processing step applied to the decoded image, in order,
+// before it's handed to the format encoder.
+#[derive(Deserialize, Clone)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ProcessOp {
+    Resize {
+        max_w: u32,
+        max_h: u32,
+        #[serde(default)]
+        filter: Option<String>,
+    },
+    Crop {
+        x: u32,
+        y: u32,
+        width: u32,
+        height: u32,
+    },
+    Rotate {
+        degrees: i32,
+    },
+}
+
+fn parse_filter_type(name: Option<&str>) -> image::imageops::FilterType {
+    match name.unwrap_or("lanczos3") {
+        "nearest" => image::imageops::FilterType::Nearest,
+        "triangle" => image::imageops::FilterType::Triangle,
+        "catmull_rom" => image::imageops::FilterType::CatmullRom,
+        "gaussian" => image::imageops::FilterType::Gaussian,
+        _ => image::imageops::FilterType::Lanczos3,
+    }
+}
+
+// Applies the requested ops in order. Resize fits the image within
+// `max_w`x`max_h` (preserving aspect ratio) rather than forcing exact
+// dimensions, matching how users expect a "max size" setting to behave.
+fn apply_process_ops(dyn_img: image::DynamicImage, ops: &[ProcessOp]) -> image::DynamicImage {
+    ops.iter().fold(dyn_img, |img, op| match op {
+        ProcessOp::Resize {
+            max_w,
+            max_h,
+            filter,
+        } => img.resize(*max_w, *max_h, parse_filter_type(filter.as_deref())),
+        ProcessOp::Crop {
+            x,
+            y,
+            width,
+            height,
+        } => img.crop_imm(*x, *y, *width, *height),
+        ProcessOp::Rotate { degrees } => match degrees.rem_euclid(360) {
+            90 => img.rotate90(),
+            180 => img.rotate180(),
+            270 => img.rotate270(),
+            _ => img,
+        },
+    })
 }
 
 // Struct for the backend to send progress updates
@@ -38,6 +106,7 @@ struct Thumbnail {
     path: PathBuf,
     name: String,
     data_url: String,
+    blurhash: String,
 }
 
 #[command]
@@ -55,6 +124,247 @@ fn select_folder_from_backend(app: AppHandle) -> Result<Option<PathBuf>, String>
     }
 }
 
+// Extensions that need a decode pass (HEIF or camera RAW) before they can be
+// previewed or re-encoded, because `image::open` doesn't understand them.
+fn is_exotic_extension(ext: &str) -> bool {
+    matches!(ext, "heic" | "heif" | "cr2" | "nef" | "arw" | "dng")
+}
+
+// Monotonic counter used to give each staged temp file a unique name so
+// concurrent `convert_image` calls for the same extension don't race on
+// one shared path.
+static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+fn unique_temp_path(ext: &str) -> PathBuf {
+    let mut temp_path = std::env::temp_dir();
+    let unique = TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed);
+    temp_path.push(format!(
+        "image_converter_input_{}_{}.{}",
+        std::process::id(),
+        unique,
+        ext
+    ));
+    temp_path
+}
+
+// Decodes a path into a `DynamicImage`, routing HEIF and RAW inputs through
+// their dedicated decoders and falling back to `image::open` for everything
+// `image` already understands natively.
+fn decode_image_from_path(path: &Path) -> Result<image::DynamicImage, String> {
+    let ext = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+        .unwrap_or_default();
+
+    match ext.as_str() {
+        "heic" | "heif" => decode_heif(path),
+        "cr2" | "nef" | "arw" | "dng" => decode_raw(path),
+        _ => image::open(path).map_err(|e| format!("Failed to open image: {}", e)),
+    }
+}
+
+fn decode_heif(path: &Path) -> Result<image::DynamicImage, String> {
+    let file_path = path.to_str().ok_or("Invalid HEIF file path")?;
+    let ctx = libheif_rs::HeifContext::read_from_file(file_path)
+        .map_err(|e| format!("Failed to read HEIF file: {}", e))?;
+    let handle = ctx
+        .primary_image_handle()
+        .map_err(|e| format!("Failed to get primary HEIF image: {}", e))?;
+    let heif_image = handle
+        .decode(
+            libheif_rs::ColorSpace::Rgb(libheif_rs::RgbChroma::Rgb),
+            None,
+        )
+        .map_err(|e| format!("Failed to decode HEIF image: {}", e))?;
+
+    let width = heif_image.width();
+    let height = heif_image.height();
+    let plane = heif_image
+        .planes()
+        .interleaved
+        .ok_or("HEIF image has no interleaved RGB plane")?;
+    let stride = plane.stride;
+
+    let mut rgb = image::RgbImage::new(width, height);
+    for y in 0..height {
+        let row = &plane.data[y as usize * stride..];
+        for x in 0..width {
+            let idx = x as usize * 3;
+            rgb.put_pixel(x, y, image::Rgb([row[idx], row[idx + 1], row[idx + 2]]));
+        }
+    }
+    Ok(image::DynamicImage::ImageRgb8(rgb))
+}
+
+fn decode_raw(path: &Path) -> Result<image::DynamicImage, String> {
+    let raw_image =
+        rawloader::decode_file(path).map_err(|e| format!("Failed to decode RAW file: {}", e))?;
+    let mut pipeline = imagepipe::Pipeline::new_from_raw(raw_image)
+        .map_err(|e| format!("Failed to build RAW pipeline: {}", e))?;
+    let decoded = pipeline
+        .output_8bit(None)
+        .map_err(|e| format!("Failed to develop RAW image: {}", e))?;
+    let buffer =
+        image::RgbImage::from_raw(decoded.width as u32, decoded.height as u32, decoded.data)
+            .ok_or("RAW pipeline produced an invalid buffer")?;
+    Ok(image::DynamicImage::ImageRgb8(buffer))
+}
+
+// A decoded multi-frame asset (animated GIF or animated WebP), keeping each
+// frame's image data alongside its display delay and the container's loop
+// count so a re-encode can reproduce the original animation.
+struct AnimatedImage {
+    frames: Vec<(image::DynamicImage, u32)>, // (frame, delay in ms)
+    loop_count: u32,                         // 0 means "loop forever"
+}
+
+// Whether converting from `source_ext` to `target_format` should preserve
+// animation rather than collapsing to a single frame. Only GIF and WebP can
+// carry multiple frames on either end of the conversion.
+fn wants_animated_output(source_ext: &str, target_format: &str) -> bool {
+    matches!(source_ext, "gif" | "webp") && matches!(target_format, "gif" | "webp")
+}
+
+// Decodes `bytes` as a multi-frame asset if `source_ext` is an animated
+// format. Returns `Ok(None)` for anything else (or a single-frame GIF/WebP),
+// letting the caller fall back to the existing still-image path.
+fn decode_animated(bytes: &[u8], source_ext: &str) -> Result<Option<AnimatedImage>, String> {
+    let anim = match source_ext {
+        "gif" => Some(decode_animated_gif(bytes)?),
+        "webp" => decode_animated_webp(bytes)?,
+        _ => None,
+    };
+    Ok(anim.filter(|anim| anim.frames.len() > 1))
+}
+
+fn decode_animated_gif(bytes: &[u8]) -> Result<AnimatedImage, String> {
+    let decoder = image::codecs::gif::GifDecoder::new(Cursor::new(bytes))
+        .map_err(|e| format!("Failed to read GIF: {}", e))?;
+    let mut frames = Vec::new();
+    for frame in decoder.into_frames() {
+        let frame = frame.map_err(|e| format!("Failed to decode GIF frame: {}", e))?;
+        let (numer, denom) = frame.delay().numer_denom_ms();
+        let delay_ms = if denom == 0 { numer } else { numer / denom };
+        frames.push((
+            image::DynamicImage::ImageRgba8(frame.into_buffer()),
+            delay_ms,
+        ));
+    }
+    // The `image` crate doesn't surface the Netscape loop-count extension,
+    // so animated GIFs round-trip as "loop forever".
+    Ok(AnimatedImage {
+        frames,
+        loop_count: 0,
+    })
+}
+
+fn decode_animated_webp(bytes: &[u8]) -> Result<Option<AnimatedImage>, String> {
+    let anim = webp::AnimDecoder::new(bytes)
+        .decode()
+        .map_err(|e| format!("Failed to decode animated WebP: {:?}", e))?;
+    let webp_frames = anim.get_frames(..);
+    if webp_frames.len() <= 1 {
+        return Ok(None);
+    }
+    let mut frames = Vec::new();
+    let mut prev_timestamp_ms = 0i32;
+    for frame in webp_frames {
+        let timestamp_ms = frame.get_time_ms();
+        let delay_ms = (timestamp_ms - prev_timestamp_ms).max(0) as u32;
+        prev_timestamp_ms = timestamp_ms;
+        frames.push((frame.get_image(), delay_ms));
+    }
+    Ok(Some(AnimatedImage {
+        frames,
+        loop_count: anim.loop_count(),
+    }))
+}
+
+// Re-encodes a decoded multi-frame asset as an animated GIF or animated
+// WebP, preserving per-frame delays and the source loop count.
+fn encode_animated(anim: &AnimatedImage, target_format: &str) -> Result<Vec<u8>, String> {
+    match target_format {
+        "gif" => encode_animated_gif(anim),
+        "webp" => encode_animated_webp(anim),
+        _ => Err("Unsupported animated output format".to_string()),
+    }
+}
+
+fn encode_animated_gif(anim: &AnimatedImage) -> Result<Vec<u8>, String> {
+    let mut buf = Vec::new();
+    {
+        let mut encoder = image::codecs::gif::GifEncoder::new(&mut buf);
+        let repeat = if anim.loop_count == 0 {
+            image::codecs::gif::Repeat::Infinite
+        } else {
+            image::codecs::gif::Repeat::Finite(anim.loop_count as u16)
+        };
+        encoder
+            .set_repeat(repeat)
+            .map_err(|e| format!("Failed to set GIF loop count: {}", e))?;
+        for (frame_img, delay_ms) in &anim.frames {
+            let delay = image::Delay::from_numerator_denominator_ms(*delay_ms, 1);
+            let frame = image::Frame::from_parts(frame_img.to_rgba8(), 0, 0, delay);
+            encoder
+                .encode_frame(frame)
+                .map_err(|e| format!("Failed to encode GIF frame: {}", e))?;
+        }
+    }
+    Ok(buf)
+}
+
+fn encode_animated_webp(anim: &AnimatedImage) -> Result<Vec<u8>, String> {
+    let (width, height) = anim
+        .frames
+        .first()
+        .map(|(img, _)| (img.width(), img.height()))
+        .ok_or("Animated asset has no frames")?;
+
+    let rgba_frames: Vec<image::RgbaImage> =
+        anim.frames.iter().map(|(img, _)| img.to_rgba8()).collect();
+
+    let mut encoder = webp::AnimEncoder::new(width, height);
+    encoder.set_loop_count(anim.loop_count as i32);
+    let mut timestamp_ms = 0i32;
+    for (rgba_frame, (_, delay_ms)) in rgba_frames.iter().zip(&anim.frames) {
+        timestamp_ms += *delay_ms as i32;
+        encoder.add_frame(webp::AnimFrame::from_rgba(
+            rgba_frame,
+            width,
+            height,
+            timestamp_ms,
+        ));
+    }
+    Ok(encoder.encode().to_vec())
+}
+
+// Decodes `bytes` as an animated asset and, if `format` asked for an
+// animated target, applies `ops` to every frame and writes the re-encoded
+// sequence to `output_dir`. Returns `Ok(None)` when the source/target pair
+// isn't animated, so the caller can fall back to the single-frame path.
+fn try_process_animated(
+    bytes: &[u8],
+    source_ext: &str,
+    filename: &str,
+    format: &str,
+    output_dir: &Path,
+    is_batch: bool,
+    ops: &[ProcessOp],
+) -> Result<Option<String>, String> {
+    if !wants_animated_output(source_ext, format) {
+        return Ok(None);
+    }
+    let Some(mut anim) = decode_animated(bytes, source_ext)? else {
+        return Ok(None);
+    };
+    for (frame_img, _) in anim.frames.iter_mut() {
+        *frame_img = apply_process_ops(frame_img.clone(), ops);
+    }
+    let encoded = encode_animated(&anim, format)?;
+    write_output_file(output_dir, filename, is_batch, format, encoded).map(Some)
+}
+
 #[command]
 fn get_image_thumbnails(folder_path: String) -> Result<Vec<Thumbnail>, String> {
     let entries =
@@ -68,100 +378,178 @@ fn get_image_thumbnails(folder_path: String) -> Result<Vec<Thumbnail>, String> {
                     .extension()
                     .and_then(|s| s.to_str())
                     .map_or(false, |ext| {
-                        matches!(ext.to_lowercase().as_str(), "png" | "jpg" | "jpeg" | "webp")
+                        let ext = ext.to_lowercase();
+                        matches!(ext.as_str(), "png" | "jpg" | "jpeg" | "webp")
+                            || is_exotic_extension(&ext)
                     })
         })
         .filter_map(|path| {
-            let mime_type = match path.extension().and_then(|s| s.to_str()) {
-                Some("png") => "image/png",
-                Some("jpg") | Some("jpeg") => "image/jpeg",
-                Some("webp") => "image/webp",
-                _ => return None,
-            };
-            if let Ok(bytes) = fs::read(&path) {
-                let base64_str = general_purpose::STANDARD.encode(&bytes);
-                let data_url = format!("data:{};base64,{}", mime_type, base64_str);
-                let name = path
-                    .file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .to_string();
-                Some(Thumbnail {
-                    path,
-                    name,
-                    data_url,
-                })
+            let ext = path
+                .extension()
+                .and_then(|s| s.to_str())
+                .map(|s| s.to_lowercase())
+                .unwrap_or_default();
+            let dyn_img = decode_image_from_path(&path).ok()?;
+            let (bytes, mime_type) = if is_exotic_extension(&ext) {
+                let mut buf = Vec::new();
+                dyn_img
+                    .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+                    .ok()?;
+                (buf, "image/png")
             } else {
-                None
-            }
+                let mime_type = match ext.as_str() {
+                    "png" => "image/png",
+                    "jpg" | "jpeg" => "image/jpeg",
+                    "webp" => "image/webp",
+                    _ => return None,
+                };
+                (fs::read(&path).ok()?, mime_type)
+            };
+            let blurhash = blurhash::encode(&dyn_img.to_rgb8(), 4, 3);
+            let base64_str = general_purpose::STANDARD.encode(&bytes);
+            let data_url = format!("data:{};base64,{}", mime_type, base64_str);
+            let name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            Some(Thumbnail {
+                path,
+                name,
+                data_url,
+                blurhash,
+            })
         })
         .collect();
     Ok(thumbnails)
 }
 
+// Alpha-composites an RGBA image onto a flat background color, used whenever
+// a format can't keep transparency (or the user asked for a matte anyway).
+fn composite_over_background(rgba_img: &image::RgbaImage, color: [u8; 3]) -> image::RgbImage {
+    let mut background = image::RgbImage::new(rgba_img.width(), rgba_img.height());
+    for pixel in background.pixels_mut() {
+        *pixel = image::Rgb(color);
+    }
+
+    for (x, y, pixel) in rgba_img.enumerate_pixels() {
+        let alpha = pixel[3] as f32 / 255.0;
+        let bg_pixel = background.get_pixel_mut(x, y);
+        bg_pixel[0] = ((1.0 - alpha) * bg_pixel[0] as f32 + alpha * pixel[0] as f32) as u8;
+        bg_pixel[1] = ((1.0 - alpha) * bg_pixel[1] as f32 + alpha * pixel[1] as f32) as u8;
+        bg_pixel[2] = ((1.0 - alpha) * bg_pixel[2] as f32 + alpha * pixel[2] as f32) as u8;
+    }
+
+    background
+}
+
+// Shared with `cancel_conversion`: flipped to request an early stop, checked
+// by each worker between files so an in-flight batch can be aborted.
+type CancelFlag = Arc<AtomicBool>;
+
 #[command]
-async fn convert_all_images(app: AppHandle, job: ConversionJob) -> Result<(), String> {
+async fn convert_all_images(
+    app: AppHandle,
+    job: ConversionJob,
+    cancel_flag: tauri::State<'_, CancelFlag>,
+) -> Result<(), String> {
+    let cancel_flag = cancel_flag.inner().clone();
+    cancel_flag.store(false, Ordering::SeqCst);
+
     thread::spawn(move || {
         let total_files = job.files.len();
-        for (i, file_path) in job.files.iter().enumerate() {
-            let progress = ((i + 1) as f32 / total_files as f32 * 100.0) as u32;
-            let file_name = Path::new(file_path)
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string();
+        let completed = AtomicU32::new(0);
+        let was_cancelled = AtomicBool::new(false);
 
-            // Emit "processing" event
-            app.emit(
-                "conversion-progress",
-                Some(ConversionPayload {
-                    status: "processing".to_string(),
-                    message: format!("Converting {}...", file_name),
-                    progress,
-                }),
-            )
-            .unwrap();
-
-            // Perform the conversion for one file
-            let result = convert_image_from_path(
-                file_path.clone(),
-                job.format.clone(),
-                job.bg_color.clone(),
-            );
+        // Cap the worker count at the available cores rather than letting
+        // rayon's global pool (shared with the rest of the process) grow
+        // unbounded.
+        let num_threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(num_threads)
+            .build()
+            .expect("Failed to build conversion thread pool");
 
-            // Emit result event
-            match result {
-                Ok(converted_path) => {
-                    app.emit(
-                        "conversion-progress",
-                        Some(ConversionPayload {
-                            status: "success".to_string(),
-                            message: format!("✅ {} -> {}", file_name, converted_path),
-                            progress,
-                        }),
-                    )
-                    .unwrap();
+        pool.install(|| {
+            job.files.par_iter().for_each(|file_path| {
+                if cancel_flag.load(Ordering::SeqCst) {
+                    was_cancelled.store(true, Ordering::SeqCst);
+                    return;
                 }
-                Err(e) => {
-                    app.emit(
-                        "conversion-progress",
-                        Some(ConversionPayload {
-                            status: "error".to_string(),
-                            message: format!("❌ {} - {}", file_name, e),
-                            progress,
-                        }),
-                    )
-                    .unwrap();
+
+                let file_name = Path::new(file_path)
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy()
+                    .to_string();
+
+                // Emit "processing" event. The counter only reflects files
+                // that have already finished, so this undercounts slightly
+                // while files are in flight, but it stays monotonic across
+                // threads instead of racing on a per-file index.
+                app.emit(
+                    "conversion-progress",
+                    Some(ConversionPayload {
+                        status: "processing".to_string(),
+                        message: format!("Converting {}...", file_name),
+                        progress: completed.load(Ordering::SeqCst) * 100 / total_files as u32,
+                    }),
+                )
+                .unwrap();
+
+                // Perform the conversion for one file
+                let result = convert_image_from_path(
+                    file_path.clone(),
+                    job.format.clone(),
+                    job.bg_color.clone(),
+                    job.quality,
+                    job.effort,
+                    job.lossless,
+                    job.ops.clone(),
+                );
+
+                let done = completed.fetch_add(1, Ordering::SeqCst) + 1;
+                let progress = (done as f32 / total_files as f32 * 100.0) as u32;
+
+                // Emit result event
+                match result {
+                    Ok(converted_path) => {
+                        app.emit(
+                            "conversion-progress",
+                            Some(ConversionPayload {
+                                status: "success".to_string(),
+                                message: format!("✅ {} -> {}", file_name, converted_path),
+                                progress,
+                            }),
+                        )
+                        .unwrap();
+                    }
+                    Err(e) => {
+                        app.emit(
+                            "conversion-progress",
+                            Some(ConversionPayload {
+                                status: "error".to_string(),
+                                message: format!("❌ {} - {}", file_name, e),
+                                progress,
+                            }),
+                        )
+                        .unwrap();
+                    }
                 }
-            }
-        }
+            });
+        });
 
-        // Emit final "complete" event
+        // Emit final event
+        let (status, message) = if was_cancelled.load(Ordering::SeqCst) {
+            ("cancelled", "Conversion cancelled.")
+        } else {
+            ("complete", "All conversions finished.")
+        };
         app.emit(
             "conversion-progress",
             Some(ConversionPayload {
-                status: "complete".to_string(),
-                message: "All conversions finished.".to_string(),
+                status: status.to_string(),
+                message: message.to_string(),
                 progress: 100,
             }),
         )
@@ -171,13 +559,26 @@ async fn convert_all_images(app: AppHandle, job: ConversionJob) -> Result<(), St
     Ok(()) // Return immediately to unblock the frontend
 }
 
+// Requests that the in-flight `convert_all_images` batch stop launching new
+// files. Workers still check between files, so already-dispatched
+// conversions run to completion.
+#[command]
+fn cancel_conversion(cancel_flag: tauri::State<'_, CancelFlag>) {
+    cancel_flag.store(true, Ordering::SeqCst);
+}
+
 #[command]
 fn convert_image(
     file_bytes: Vec<u8>,
     filename: String,
     format: String,
     bg_color: Option<String>,
+    quality: Option<u8>,
+    effort: Option<u8>,
+    lossless: Option<bool>,
+    ops: Option<Vec<ProcessOp>>,
 ) -> Result<String, String> {
+    let ops = ops.unwrap_or_default();
     let rgb_color = if let Some(hex) = &bg_color {
         if hex.len() != 6 && hex.len() != 7 {
             return Err("Invalid hex color format".to_string());
@@ -194,14 +595,43 @@ fn convert_image(
         None
     };
 
-    let reader = Cursor::new(file_bytes);
-    let format_guess = image::guess_format(reader.get_ref())
-        .map_err(|e| format!("Failed to guess image format: {}", e))?;
-    let dyn_img =
-        image::load(reader, format_guess).map_err(|e| format!("Failed to load image: {}", e))?;
+    let source_ext = Path::new(&filename)
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+        .unwrap_or_default();
     let desktop = dirs::desktop_dir().ok_or("Failed to find Desktop directory")?;
     let output_dir = desktop.join("ImageConverter");
-    process_and_save_image(dyn_img, filename, format, rgb_color, output_dir, false)
+
+    if let Some(output_path) = try_process_animated(
+        &file_bytes,
+        &source_ext,
+        &filename,
+        &format,
+        &output_dir,
+        false,
+        &ops,
+    )? {
+        return Ok(output_path);
+    }
+
+    let dyn_img = if is_exotic_extension(&source_ext) {
+        // HEIF/RAW decoders need a real file on disk, so stage the bytes there.
+        let temp_path = unique_temp_path(&source_ext);
+        fs::write(&temp_path, &file_bytes)
+            .map_err(|e| format!("Failed to stage temporary file: {}", e))?;
+        let result = decode_image_from_path(&temp_path);
+        let _ = fs::remove_file(&temp_path);
+        result?
+    } else {
+        let reader = Cursor::new(file_bytes);
+        let format_guess = image::guess_format(reader.get_ref())
+            .map_err(|e| format!("Failed to guess image format: {}", e))?;
+        image::load(reader, format_guess).map_err(|e| format!("Failed to load image: {}", e))?
+    };
+    process_and_save_image(
+        dyn_img, filename, format, rgb_color, output_dir, false, quality, effort, lossless, ops,
+    )
 }
 
 #[command]
@@ -209,7 +639,12 @@ fn convert_image_from_path(
     file_path: String,
     format: String,
     bg_color: Option<String>,
+    quality: Option<u8>,
+    effort: Option<u8>,
+    lossless: Option<bool>,
+    ops: Option<Vec<ProcessOp>>,
 ) -> Result<String, String> {
+    let ops = ops.unwrap_or_default();
     // println!("{bg_color} getting the background values");
     let rgb_color = if let Some(hex) = &bg_color {
         if hex.len() != 6 && hex.len() != 7 {
@@ -233,8 +668,11 @@ fn convert_image_from_path(
         .ok_or("Invalid file path")?
         .to_string_lossy()
         .to_string();
-    let dyn_img =
-        image::open(path).map_err(|e| format!("Failed to open image {}: {}", file_path, e))?;
+    let source_ext = path
+        .extension()
+        .and_then(|s| s.to_str())
+        .map(|s| s.to_lowercase())
+        .unwrap_or_default();
     let source_dir = path
         .parent()
         .ok_or("Could not find parent directory of the image")?;
@@ -243,7 +681,20 @@ fn convert_image_from_path(
         .ok_or("Could not get source folder name")?
         .to_string_lossy();
     let output_dir = source_dir.join(format!("{}_converted", source_folder_name));
-    process_and_save_image(dyn_img, filename, format, rgb_color, output_dir, true)
+
+    if wants_animated_output(&source_ext, &format) {
+        let bytes = fs::read(path).map_err(|e| format!("Failed to read file: {}", e))?;
+        if let Some(output_path) =
+            try_process_animated(&bytes, &source_ext, &filename, &format, &output_dir, true, &ops)?
+        {
+            return Ok(output_path);
+        }
+    }
+
+    let dyn_img = decode_image_from_path(path)?;
+    process_and_save_image(
+        dyn_img, filename, format, rgb_color, output_dir, true, quality, effort, lossless, ops,
+    )
 }
 
 fn process_and_save_image(
@@ -253,7 +704,12 @@ fn process_and_save_image(
     rgb_color: Option<[u8; 3]>,
     output_dir: PathBuf,
     is_batch: bool,
+    quality: Option<u8>,
+    effort: Option<u8>,
+    lossless: Option<bool>,
+    ops: Vec<ProcessOp>,
 ) -> Result<String, String> {
+    let dyn_img = apply_process_ops(dyn_img, &ops);
     let (ext, encoded_data) = match format.as_str() {
         "jpg" | "jpeg" => {
             let rgba_img = dyn_img.to_rgba8();
@@ -261,55 +717,28 @@ fn process_and_save_image(
             if has_alpha_pixels {
                 if let Some(color) = rgb_color {
                     // Use the parsed RGB color
-                    let mut background = image::RgbImage::new(dyn_img.width(), dyn_img.height());
-                    for pixel in background.pixels_mut() {
-                        *pixel = image::Rgb(color);
-                    }
-
-                    for (x, y, pixel) in rgba_img.enumerate_pixels() {
-                        let alpha = pixel[3] as f32 / 255.0;
-                        let bg_pixel = background.get_pixel_mut(x, y);
-                        bg_pixel[0] =
-                            ((1.0 - alpha) * bg_pixel[0] as f32 + alpha * pixel[0] as f32) as u8;
-                        bg_pixel[1] =
-                            ((1.0 - alpha) * bg_pixel[1] as f32 + alpha * pixel[1] as f32) as u8;
-                        bg_pixel[2] =
-                            ((1.0 - alpha) * bg_pixel[2] as f32 + alpha * pixel[2] as f32) as u8;
-                    }
-
+                    let background = composite_over_background(&rgba_img, color);
                     let mut buf = Vec::new();
+                    let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+                        &mut buf,
+                        quality.unwrap_or(80),
+                    );
                     image::DynamicImage::ImageRgb8(background)
-                        .write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg)
+                        .write_with_encoder(encoder)
                         .map_err(|e| format!("Failed to write JPEG: {}", e))?;
                     ("jpg", buf)
                 } else {
-                    println!("It is going to default mode");
                     // If no background color is provided, use white as default for batch mode
                     if is_batch {
                         let default_color = [255, 255, 255]; // White
-                        let mut background =
-                            image::RgbImage::new(dyn_img.width(), dyn_img.height());
-                        for pixel in background.pixels_mut() {
-                            *pixel = image::Rgb(default_color);
-                        }
-
-                        for (x, y, pixel) in rgba_img.enumerate_pixels() {
-                            let alpha = pixel[3] as f32 / 255.0;
-                            let bg_pixel = background.get_pixel_mut(x, y);
-                            bg_pixel[0] = ((1.0 - alpha) * bg_pixel[0] as f32
-                                + alpha * pixel[0] as f32)
-                                as u8;
-                            bg_pixel[1] = ((1.0 - alpha) * bg_pixel[1] as f32
-                                + alpha * pixel[1] as f32)
-                                as u8;
-                            bg_pixel[2] = ((1.0 - alpha) * bg_pixel[2] as f32
-                                + alpha * pixel[2] as f32)
-                                as u8;
-                        }
-
+                        let background = composite_over_background(&rgba_img, default_color);
                         let mut buf = Vec::new();
+                        let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+                            &mut buf,
+                            quality.unwrap_or(80),
+                        );
                         image::DynamicImage::ImageRgb8(background)
-                            .write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg)
+                            .write_with_encoder(encoder)
                             .map_err(|e| format!("Failed to write JPEG: {}", e))?;
                         ("jpg", buf)
                     } else {
@@ -319,31 +748,90 @@ fn process_and_save_image(
                 }
             } else {
                 let mut buf = Vec::new();
+                let encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+                    &mut buf,
+                    quality.unwrap_or(80),
+                );
                 dyn_img
                     .to_rgb8()
-                    .write_to(&mut Cursor::new(&mut buf), ImageFormat::Jpeg)
+                    .write_with_encoder(encoder)
                     .map_err(|e| format!("Failed to write JPEG: {}", e))?;
                 ("jpg", buf)
             }
         }
         "png" => {
+            let compression = match quality.unwrap_or(75) {
+                0..=30 => image::codecs::png::CompressionType::Fast,
+                90..=100 => image::codecs::png::CompressionType::Best,
+                _ => image::codecs::png::CompressionType::Default,
+            };
             let mut buf = Vec::new();
+            let encoder = image::codecs::png::PngEncoder::new_with_quality(
+                &mut buf,
+                compression,
+                image::codecs::png::FilterType::Adaptive,
+            );
             dyn_img
-                .write_to(&mut Cursor::new(&mut buf), ImageFormat::Png)
+                .write_with_encoder(encoder)
                 .map_err(|e| format!("Failed to write PNG: {}", e))?;
             ("png", buf)
         }
         "webp" => {
             let rgba_img = dyn_img.to_rgba8();
             let encoder = Encoder::from_rgba(&rgba_img, rgba_img.width(), rgba_img.height());
-            let quality = 75f32;
-            let webp_data = encoder.encode(quality);
+            let webp_data = if lossless.unwrap_or(false) {
+                encoder.encode_lossless()
+            } else {
+                encoder.encode(quality.unwrap_or(75) as f32)
+            };
             ("webp", webp_data.to_vec())
         }
+        "avif" => {
+            let avif_quality = quality.unwrap_or(80).min(100) as f32;
+            let avif_speed = effort.unwrap_or(6).clamp(1, 10);
+            let rgba_img = dyn_img.to_rgba8();
+            let (width, height) = (rgba_img.width() as usize, rgba_img.height() as usize);
+            let encoder = ravif::Encoder::new()
+                .with_quality(avif_quality)
+                .with_speed(avif_speed);
+            let avif_data = if let Some(color) = rgb_color {
+                // User asked for a matte, so flatten to an opaque RGB buffer.
+                let rgb_img = composite_over_background(&rgba_img, color);
+                let pixels = rgb_img.as_raw().as_rgb();
+                let img = ravif::Img::new(pixels, width, height);
+                encoder
+                    .encode_rgb(img)
+                    .map_err(|e| format!("Failed to encode AVIF: {}", e))?
+                    .avif_file
+            } else {
+                // AVIF supports alpha natively, so keep transparency by default.
+                let pixels = rgba_img.as_raw().as_rgba();
+                let img = ravif::Img::new(pixels, width, height);
+                encoder
+                    .encode_rgba(img)
+                    .map_err(|e| format!("Failed to encode AVIF: {}", e))?
+                    .avif_file
+            };
+            ("avif", avif_data)
+        }
         _ => return Err("Unsupported output format".to_string()),
     };
 
-    std::fs::create_dir_all(&output_dir)
+    write_output_file(&output_dir, &filename, is_batch, ext, encoded_data)
+}
+
+// Creates `output_dir` if needed and writes `encoded_data` alongside a
+// filename derived from the source `filename` and the target `ext`,
+// matching the existing "_converted" suffix convention for single-file
+// conversions.
+fn write_output_file(
+    output_dir: &Path,
+    filename: &str,
+    is_batch: bool,
+    ext: &str,
+    encoded_data: Vec<u8>,
+) -> Result<String, String> {
+    std::fs::create_dir_all(output_dir)
         .map_err(|e| format!("Failed to create output folder: {}", e))?;
     let base_name = filename.split('.').next().unwrap_or("converted");
     let output_filename = if is_batch {
@@ -364,11 +852,13 @@ fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(CancelFlag::new(AtomicBool::new(false)))
         .invoke_handler(tauri::generate_handler![
             convert_image,
             select_folder_from_backend,
             get_image_thumbnails,
-            convert_all_images
+            convert_all_images,
+            cancel_conversion
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");